@@ -1,15 +1,17 @@
 //! Client header advertising available compression algorithms.
 
-use crate::content::EncodingProposal;
+use crate::content::encoding::parse_weight;
+use crate::content::{Encoding, EncodingProposal};
 use crate::headers::{HeaderName, HeaderValue, Headers, ToHeaderValues, ACCEPT_ENCODING};
 
 use std::fmt::{self, Debug, Write};
+use std::iter::FromIterator;
 use std::option;
 use std::slice;
 
 /// Client header advertising available compression algorithms.
 pub struct AcceptEncoding {
-    wildcard: bool,
+    wildcard_weight: Option<f32>,
     entries: Vec<EncodingProposal>,
 }
 
@@ -18,7 +20,7 @@ impl AcceptEncoding {
     pub fn new() -> Self {
         Self {
             entries: vec![],
-            wildcard: false,
+            wildcard_weight: None,
         }
     }
 
@@ -30,17 +32,26 @@ impl AcceptEncoding {
             None => return Ok(None),
         };
 
-        let mut wildcard = false;
+        let mut wildcard_weight = None;
 
         for value in headers {
             for part in value.as_str().trim().split(',') {
                 let part = part.trim();
 
-                // Handle empty strings, and wildcard directives.
+                // Handle empty strings.
                 if part.is_empty() {
                     continue;
-                } else if part == "*" {
-                    wildcard = true;
+                }
+
+                // Handle wildcard directives, which may carry their own
+                // `;q=` weight (e.g. `*;q=0.1`, or bare `*`).
+                let mut directive = part.splitn(2, ';');
+                if directive.next().unwrap_or("").trim() == "*" {
+                    let weight = match directive.next() {
+                        Some(s) => parse_weight(s)?,
+                        None => 1.0,
+                    };
+                    wildcard_weight = Some(weight);
                     continue;
                 }
 
@@ -52,7 +63,10 @@ impl AcceptEncoding {
             }
         }
 
-        Ok(Some(Self { entries, wildcard }))
+        Ok(Some(Self {
+            entries,
+            wildcard_weight,
+        }))
     }
 
     /// Push a directive into the list of entries.
@@ -60,14 +74,43 @@ impl AcceptEncoding {
         self.entries.push(prop.into());
     }
 
+    /// Create an instance of `AcceptEncoding` from an iterator of items that
+    /// convert into `EncodingProposal`, e.g. `Encoding` or `EncodingProposal`
+    /// itself.
+    ///
+    /// This is distinct from the `FromIterator<EncodingProposal>` impl (used
+    /// via `.collect()`) so that callers can pass in bare `Encoding`s without
+    /// converting them first.
+    pub fn from_proposals(iter: impl IntoIterator<Item = impl Into<EncodingProposal>>) -> Self {
+        let mut accept = Self::new();
+        for entry in iter {
+            accept.push(entry);
+        }
+        accept
+    }
+
     /// Returns `true` if a wildcard directive was passed.
     pub fn wildcard(&self) -> bool {
-        self.wildcard
+        self.wildcard_weight.is_some()
     }
 
     /// Set the wildcard directive.
     pub fn set_wildcard(&mut self, wildcard: bool) {
-        self.wildcard = wildcard
+        self.wildcard_weight = if wildcard { Some(1.0) } else { None };
+    }
+
+    /// Get the q-value of the wildcard directive, if one was passed.
+    ///
+    /// `None` means no wildcard directive was passed at all; it does not
+    /// mean the wildcard's weight is zero.
+    pub fn wildcard_weight(&self) -> Option<f32> {
+        self.wildcard_weight
+    }
+
+    /// Set the q-value of the wildcard directive. Passing `None` removes
+    /// the wildcard directive entirely.
+    pub fn set_wildcard_weight(&mut self, weight: Option<f32>) {
+        self.wildcard_weight = weight;
     }
 
     /// Insert a `HeaderName` + `HeaderValue` pair into a `Headers` instance.
@@ -91,11 +134,16 @@ impl AcceptEncoding {
             };
         }
 
-        if self.wildcard {
+        if let Some(weight) = self.wildcard_weight {
+            let directive = if weight == 1.0 {
+                "*".to_owned()
+            } else {
+                format!("*;q={}", weight)
+            };
             match output.len() {
-                0 => write!(output, "*").unwrap(),
-                _ => write!(output, ", *").unwrap(),
-            }
+                0 => write!(output, "{}", directive).unwrap(),
+                _ => write!(output, ", {}", directive).unwrap(),
+            };
         }
 
         // SAFETY: the internal string is validated to be ASCII.
@@ -115,6 +163,75 @@ impl AcceptEncoding {
             inner: self.entries.iter_mut(),
         }
     }
+
+    /// Sort the entries by their q-value, descending. Entries with no
+    /// explicit weight are treated as having a weight of `1.0`. Entries with
+    /// an equal weight keep their relative order.
+    pub fn sort_by_weight(&mut self) {
+        self.entries.sort_by(|a, b| {
+            let a = a.weight.unwrap_or(1.0);
+            let b = b.weight.unwrap_or(1.0);
+            b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    /// Consume `self` and return an iterator over the entries, sorted by
+    /// their q-value in descending order.
+    ///
+    /// This is useful for consumers that want to walk the proposals in
+    /// order of preference and take the first one they can satisfy.
+    pub fn sorted(mut self) -> IntoIter {
+        self.sort_by_weight();
+        self.into_iter()
+    }
+
+    /// Negotiate which encoding to use based on the encodings the server has
+    /// `available`, in order of the server's own preference.
+    ///
+    /// This picks the encoding in `available` with the highest effective
+    /// q-value, breaking ties by the order `available` is given in. An
+    /// encoding that is not listed takes the q-value of the `*` wildcard, and
+    /// is not acceptable if there is no wildcard. Per RFC 7231 section 5.3.4,
+    /// `identity` is the exception: when it is neither listed nor covered by
+    /// a wildcard it is acceptable with a q-value of `1.0`. It is excluded
+    /// when listed with a q-value of `0`, or by a `*` wildcard with a q-value
+    /// of `0`.
+    ///
+    /// # Errors
+    ///
+    /// If no encoding in `available` is acceptable, an error is returned. The
+    /// caller should respond with a `406 Not Acceptable` status code.
+    pub fn negotiate(&self, available: &[Encoding]) -> crate::Result<Encoding> {
+        let mut candidate: Option<(&Encoding, f32)> = None;
+        for encoding in available {
+            let weight = match self.entries.iter().find(|entry| &entry.encoding == encoding) {
+                Some(entry) => entry.weight.unwrap_or(1.0),
+                None => match encoding {
+                    Encoding::Identity => self.wildcard_weight.unwrap_or(1.0),
+                    _ => match self.wildcard_weight {
+                        Some(weight) => weight,
+                        None => continue,
+                    },
+                },
+            };
+
+            if weight <= 0.0 {
+                continue;
+            }
+
+            if candidate.map_or(true, |(_, current)| weight > current) {
+                candidate = Some((encoding, weight));
+            }
+        }
+
+        match candidate {
+            Some((encoding, _)) => Ok(encoding.clone()),
+            None => Err(crate::Error::from_str(
+                406,
+                "No requested encoding is acceptable",
+            )),
+        }
+    }
 }
 
 impl IntoIterator for AcceptEncoding {
@@ -149,6 +266,16 @@ impl<'a> IntoIterator for &'a mut AcceptEncoding {
     }
 }
 
+impl FromIterator<EncodingProposal> for AcceptEncoding {
+    fn from_iter<I: IntoIterator<Item = EncodingProposal>>(iter: I) -> Self {
+        let mut accept = Self::new();
+        for entry in iter {
+            accept.push(entry);
+        }
+        accept
+    }
+}
+
 /// A borrowing iterator over entries in `AcceptEncoding`.
 #[derive(Debug)]
 pub struct IntoIter {
@@ -270,4 +397,128 @@ mod test {
         assert_eq!(accept.iter().next().unwrap(), Encoding::Gzip);
         Ok(())
     }
+
+    #[test]
+    fn sorted_orders_by_descending_weight() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(Encoding::Gzip.proposal(Some(0.2))?);
+        accept.push(Encoding::Deflate.proposal(Some(0.9))?);
+        accept.push(Encoding::Brotli);
+
+        let sorted: Vec<_> = accept.sorted().map(|entry| entry.encoding()).collect();
+        assert_eq!(sorted, vec![Encoding::Brotli, Encoding::Deflate, Encoding::Gzip]);
+        Ok(())
+    }
+
+    #[test]
+    fn negotiate_picks_highest_weight() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(Encoding::Gzip.proposal(Some(0.5))?);
+        accept.push(Encoding::Deflate.proposal(Some(0.8))?);
+
+        let picked = accept.negotiate(&[Encoding::Gzip, Encoding::Deflate])?;
+        assert_eq!(picked, Encoding::Deflate);
+        Ok(())
+    }
+
+    #[test]
+    fn negotiate_falls_back_to_identity() -> crate::Result<()> {
+        let accept = AcceptEncoding::new();
+        let picked = accept.negotiate(&[Encoding::Gzip, Encoding::Identity])?;
+        assert_eq!(picked, Encoding::Identity);
+        Ok(())
+    }
+
+    #[test]
+    fn negotiate_rejects_forbidden_identity() {
+        let mut accept = AcceptEncoding::new();
+        accept.set_wildcard(true);
+        accept.push(Encoding::Identity.proposal(Some(0.0)).unwrap());
+
+        assert!(accept.negotiate(&[Encoding::Identity]).is_err());
+    }
+
+    #[test]
+    fn wildcard_weight_roundtrips_through_headers() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(Encoding::Brotli.proposal(Some(1.0))?);
+        accept.set_wildcard_weight(Some(0.1));
+
+        let mut headers = Response::new(200);
+        accept.apply(&mut headers);
+
+        let accept = AcceptEncoding::from_headers(headers)?.unwrap();
+        assert!(accept.wildcard());
+        assert_eq!(accept.wildcard_weight(), Some(0.1));
+        Ok(())
+    }
+
+    #[test]
+    fn wildcard_with_space_before_weight_still_parses() -> crate::Result<()> {
+        let mut headers = Response::new(200);
+        headers.insert_header(ACCEPT_ENCODING, "gzip, * ; q=0.1");
+
+        let accept = AcceptEncoding::from_headers(headers)?.unwrap();
+        assert!(accept.wildcard());
+        assert_eq!(accept.wildcard_weight(), Some(0.1));
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_range_wildcard_weight_is_rejected() {
+        let mut headers = Response::new(200);
+        headers.insert_header(ACCEPT_ENCODING, "gzip;q=1.0, *;q=5.0");
+
+        assert!(AcceptEncoding::from_headers(headers).is_err());
+    }
+
+    #[test]
+    fn negotiate_gives_identity_the_wildcard_weight() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(Encoding::Brotli.proposal(Some(1.0))?);
+        accept.set_wildcard_weight(Some(0.1));
+
+        // Neither Gzip nor Identity is listed explicitly, so both fall back
+        // to the low wildcard weight; the first in `available` wins the tie.
+        let picked = accept.negotiate(&[Encoding::Gzip, Encoding::Identity])?;
+        assert_eq!(picked, Encoding::Gzip);
+
+        // Brotli's explicit q=1.0 outranks identity's wildcard-derived 0.1.
+        assert_eq!(
+            accept.negotiate(&[Encoding::Brotli, Encoding::Identity])?,
+            Encoding::Brotli
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn negotiate_excludes_identity_via_zero_wildcard_weight() {
+        let mut accept = AcceptEncoding::new();
+        accept.set_wildcard_weight(Some(0.0));
+
+        assert!(accept.negotiate(&[Encoding::Identity]).is_err());
+    }
+
+    #[test]
+    fn from_proposals_collects_encodings() {
+        let accept = AcceptEncoding::from_proposals([Encoding::Gzip, Encoding::Brotli]);
+        let entries: Vec<_> = accept.iter().map(|entry| entry.encoding()).collect();
+        assert_eq!(entries, vec![Encoding::Gzip, Encoding::Brotli]);
+    }
+
+    #[test]
+    fn collect_from_filtered_proposals() -> crate::Result<()> {
+        let proposals = vec![
+            Encoding::Gzip.proposal(Some(0.5))?,
+            Encoding::Deflate.proposal(Some(0.0))?,
+        ];
+
+        let accept: AcceptEncoding = proposals
+            .into_iter()
+            .filter(|entry| entry.weight() != Some(0.0))
+            .collect();
+
+        assert_eq!(accept.iter().next().unwrap(), Encoding::Gzip);
+        Ok(())
+    }
 }
@@ -0,0 +1,206 @@
+//! Available compression algorithms.
+
+use crate::headers::HeaderValue;
+
+use std::fmt::{self, Display};
+
+/// Available compression algorithms.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// The Gzip encoding.
+    Gzip,
+    /// The Deflate encoding.
+    Deflate,
+    /// The Compress encoding.
+    Compress,
+    /// The Identity encoding.
+    Identity,
+    /// The Brotli encoding.
+    Brotli,
+    /// The Zstd encoding.
+    Zstd,
+}
+
+impl Encoding {
+    /// Parse a string into an encoding, returning `None` if the string is not
+    /// a recognized encoding.
+    pub(crate) fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "gzip" => Some(Self::Gzip),
+            "deflate" => Some(Self::Deflate),
+            "compress" => Some(Self::Compress),
+            "identity" => Some(Self::Identity),
+            "br" => Some(Self::Brotli),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Get the string representation of the encoding.
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+            Self::Compress => "compress",
+            Self::Identity => "identity",
+            Self::Brotli => "br",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Create a proposal from an encoding.
+    pub fn proposal(self, weight: Option<f32>) -> crate::Result<EncodingProposal> {
+        EncodingProposal::new(self, weight)
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+impl From<Encoding> for HeaderValue {
+    fn from(encoding: Encoding) -> Self {
+        let s = encoding.to_str();
+        // SAFETY: the encoding string is always ASCII.
+        unsafe { HeaderValue::from_bytes_unchecked(s.as_bytes().to_owned()) }
+    }
+}
+
+impl PartialEq<Encoding> for EncodingProposal {
+    fn eq(&self, other: &Encoding) -> bool {
+        &self.encoding == other
+    }
+}
+
+impl PartialEq<EncodingProposal> for Encoding {
+    fn eq(&self, other: &EncodingProposal) -> bool {
+        self == &other.encoding
+    }
+}
+
+/// An encoding, combined with its q-value weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingProposal {
+    pub(crate) encoding: Encoding,
+    pub(crate) weight: Option<f32>,
+}
+
+impl EncodingProposal {
+    /// Create a new instance of `EncodingProposal`.
+    pub fn new(encoding: Encoding, weight: Option<f32>) -> crate::Result<Self> {
+        if let Some(weight) = weight {
+            validate_weight(weight)?;
+        }
+        Ok(Self { encoding, weight })
+    }
+
+    /// Parse a single encoding directive, e.g. `gzip;q=0.8`.
+    pub(crate) fn from_str(s: &str) -> crate::Result<Option<Self>> {
+        let mut parts = s.split(';');
+
+        let encoding = match Encoding::from_str(parts.next().unwrap_or("").trim()) {
+            Some(encoding) => encoding,
+            None => return Ok(None),
+        };
+
+        let weight = match parts.next() {
+            Some(s) => Some(parse_weight(s)?),
+            None => None,
+        };
+
+        Ok(Some(Self::new(encoding, weight)?))
+    }
+
+    /// Get the encoding.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding.clone()
+    }
+
+    /// Get the weight of the proposal.
+    ///
+    /// This is used to determine which encoding is preferred. `None` is
+    /// treated as `1.0`. `new` and header parsing only accept values between
+    /// 0.0 and 1.0; `set_weight` does not check the range.
+    pub fn weight(&self) -> Option<f32> {
+        self.weight
+    }
+
+    /// Set the weight of the proposal.
+    pub fn set_weight(&mut self, weight: Option<f32>) {
+        self.weight = weight;
+    }
+}
+
+impl From<Encoding> for EncodingProposal {
+    fn from(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            weight: None,
+        }
+    }
+}
+
+impl From<EncodingProposal> for HeaderValue {
+    fn from(entry: EncodingProposal) -> Self {
+        let mut s = entry.encoding.to_str().to_owned();
+        if let Some(weight) = entry.weight {
+            s.push_str(&format!(";q={}", weight));
+        }
+        // SAFETY: the encoding and weight are always ASCII.
+        unsafe { HeaderValue::from_bytes_unchecked(s.into_bytes()) }
+    }
+}
+
+/// Parse a `q=0.8` style weight directive.
+pub(crate) fn parse_weight(s: &str) -> crate::Result<f32> {
+    let mut parts = s.trim().splitn(2, '=');
+    let key = parts.next().unwrap_or("").trim();
+    let value = parts.next().unwrap_or("").trim();
+
+    if key != "q" {
+        return Err(crate::Error::from_str(
+            400,
+            "Expected a `q=` directive in encoding weight",
+        ));
+    }
+
+    let weight: f32 = value
+        .parse()
+        .map_err(|_| crate::Error::from_str(400, "Could not parse encoding weight"))?;
+    validate_weight(weight)?;
+    Ok(weight)
+}
+
+/// Ensure a q-value weight falls within the `0.0..=1.0` range mandated by
+/// RFC 7231 section 5.3.1.
+pub(crate) fn validate_weight(weight: f32) -> crate::Result<f32> {
+    if !(0.0..=1.0).contains(&weight) {
+        return Err(crate::Error::from_str(
+            400,
+            "Encoding weight must be between 0.0 and 1.0",
+        ));
+    }
+    Ok(weight)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip_brotli_and_zstd() -> crate::Result<()> {
+        assert_eq!(Encoding::from_str("br"), Some(Encoding::Brotli));
+        assert_eq!(Encoding::from_str("zstd"), Some(Encoding::Zstd));
+        assert_eq!(Encoding::Brotli.to_str(), "br");
+        assert_eq!(Encoding::Zstd.to_str(), "zstd");
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_encoding_is_none() {
+        assert_eq!(Encoding::from_str("bogus"), None);
+    }
+}
@@ -0,0 +1,7 @@
+//! HTTP content negotiation headers.
+
+mod accept_encoding;
+mod encoding;
+
+pub use accept_encoding::AcceptEncoding;
+pub use encoding::{Encoding, EncodingProposal};